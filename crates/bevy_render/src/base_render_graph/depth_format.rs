@@ -0,0 +1,51 @@
+use crate::{
+    pass::Operations,
+    renderer::RenderResourceContext,
+    texture::{TextureFormat, TextureUsage},
+};
+
+/// Depth formats to try, in order of preference, when no explicit override is given.
+/// This mirrors the common Vulkan "find supported format" pattern: probe each candidate's
+/// format features on the active backend and take the first one that supports being used
+/// as a depth-stencil attachment.
+const DEPTH_FORMAT_CANDIDATES: &[TextureFormat] = &[
+    TextureFormat::Depth24Plus,
+    TextureFormat::Depth24PlusStencil8,
+    TextureFormat::Depth32Float,
+];
+
+/// Resolves the texture format to use for the main depth texture.
+///
+/// If `explicit` is set it is returned unmodified, giving callers an escape hatch. Otherwise
+/// each format in [`DEPTH_FORMAT_CANDIDATES`] is probed against `render_resource_context` and
+/// the first one that supports the depth-stencil attachment usage is selected, falling back
+/// to `TextureFormat::Depth32Float` if the backend doesn't report support for any candidate.
+pub fn get_depth_format(
+    render_resource_context: &dyn RenderResourceContext,
+    explicit: Option<TextureFormat>,
+) -> TextureFormat {
+    if let Some(format) = explicit {
+        return format;
+    }
+
+    DEPTH_FORMAT_CANDIDATES
+        .iter()
+        .copied()
+        .find(|format| {
+            render_resource_context.supports_texture_usage(*format, TextureUsage::OUTPUT_ATTACHMENT)
+        })
+        .unwrap_or(TextureFormat::Depth32Float)
+}
+
+/// The stencil `Operations` to use for a `RenderPassDepthStencilAttachmentDescriptor` bound to
+/// a texture of `depth_format`, or `None` if `depth_format` has no stencil aspect to operate
+/// on. Every pass that binds a depth texture created with `depth_format` (`MAIN_PASS`,
+/// `TRANSPARENT_PASS`, `DEPTH_PREPASS`) must go through this so they agree on whether a
+/// stencil aspect is present, instead of each re-deriving it (and potentially disagreeing).
+pub fn stencil_ops_for_format(depth_format: TextureFormat, ops: Operations<u32>) -> Option<Operations<u32>> {
+    if depth_format == TextureFormat::Depth24PlusStencil8 {
+        Some(ops)
+    } else {
+        None
+    }
+}
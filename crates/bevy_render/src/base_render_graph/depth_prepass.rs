@@ -0,0 +1,114 @@
+use bevy_ecs::world::World;
+
+use crate::{
+    render_graph::{Node, ResourceSlotInfo, ResourceSlots},
+    renderer::{RenderContext, RenderResourceContext, RenderResourceId, RenderResourceType},
+    texture::{TextureDescriptor, TextureFormat, TextureId, TextureUsage},
+};
+
+/// Returns `true` if the active backend can blit directly between two depth-stencil textures
+/// of `format`. Many tiled mobile GPUs cannot blit a depth-stencil attachment while it is
+/// still bound, so [`DepthBlitNode`] falls back to a full-screen depth-copy material pass when
+/// this returns `false`.
+///
+/// This is a distinct capability from `format`/`SAMPLED` support: `depth_format` is already
+/// chosen for `OUTPUT_ATTACHMENT` support, and the prepass texture is always created with
+/// `SAMPLED | OUTPUT_ATTACHMENT`, so checking `SAMPLED` support here would always report
+/// `true` and never actually exercise the fallback path.
+pub fn is_depth_stencil_blit_supported(
+    render_resource_context: &dyn RenderResourceContext,
+    format: TextureFormat,
+) -> bool {
+    render_resource_context.supports_depth_stencil_blit(format)
+}
+
+/// Copies the populated depth-prepass texture into a separately sampleable texture, so
+/// downstream effects (SSAO, soft particles, fog, ...) can read scene depth without each
+/// declaring its own prepass.
+///
+/// On backends where [`is_depth_stencil_blit_supported`] reports support this issues a single
+/// texture-to-texture blit. Otherwise it draws a full-screen quad using a depth-copy material
+/// that samples the source depth texture and writes its value into the destination.
+pub struct DepthBlitNode {
+    supports_native_blit: bool,
+    destination: Option<(TextureDescriptor, TextureId)>,
+}
+
+impl DepthBlitNode {
+    pub const IN_TEXTURE: &'static str = "depth_prepass_texture";
+    pub const OUT_TEXTURE: &'static str = "sampled_depth_texture";
+
+    pub fn new(render_resource_context: &dyn RenderResourceContext, format: TextureFormat) -> Self {
+        DepthBlitNode {
+            supports_native_blit: is_depth_stencil_blit_supported(render_resource_context, format),
+            destination: None,
+        }
+    }
+}
+
+impl Node for DepthBlitNode {
+    fn input(&self) -> &[ResourceSlotInfo] {
+        static INPUT: &[ResourceSlotInfo] = &[ResourceSlotInfo {
+            name: std::borrow::Cow::Borrowed(DepthBlitNode::IN_TEXTURE),
+            resource_type: RenderResourceType::Texture,
+        }];
+        INPUT
+    }
+
+    fn output(&self) -> &[ResourceSlotInfo] {
+        static OUTPUT: &[ResourceSlotInfo] = &[ResourceSlotInfo {
+            name: std::borrow::Cow::Borrowed(DepthBlitNode::OUT_TEXTURE),
+            resource_type: RenderResourceType::Texture,
+        }];
+        OUTPUT
+    }
+
+    fn update(
+        &mut self,
+        _world: &World,
+        render_context: &mut dyn RenderContext,
+        input: &ResourceSlots,
+        output: &mut ResourceSlots,
+    ) {
+        let source = input.get(0).texture.unwrap();
+
+        // `output` starts every frame with nothing allocated for `OUT_TEXTURE` — unlike
+        // `WindowTextureNode`, nothing upstream creates this resource, so we have to allocate
+        // it ourselves (matching the source's size/format) before anything can copy into it.
+        // It needs both `SAMPLED` (so downstream effects can read it) and `OUTPUT_ATTACHMENT`:
+        // the fallback path below renders into it via `run_depth_copy_material_pass`, just
+        // like `DEPTH_PREPASS_TEXTURE` is created with both for the same reason.
+        let destination = {
+            let resources = render_context.resources();
+            let mut descriptor = resources
+                .texture_descriptor(source)
+                .expect("DepthBlitNode input texture must have a descriptor");
+            descriptor.usage = TextureUsage::SAMPLED | TextureUsage::OUTPUT_ATTACHMENT;
+
+            match &self.destination {
+                Some((cached_descriptor, texture_id)) if *cached_descriptor == descriptor => {
+                    *texture_id
+                }
+                _ => {
+                    if let Some((_, stale_texture_id)) = self.destination.take() {
+                        resources.remove_texture(stale_texture_id);
+                    }
+                    let texture_id = resources.create_texture(descriptor.clone());
+                    self.destination = Some((descriptor, texture_id));
+                    texture_id
+                }
+            }
+        };
+
+        output.set(0, RenderResourceId::Texture(destination));
+
+        if self.supports_native_blit {
+            render_context.copy_texture_to_texture(source, destination);
+        } else {
+            // PERF: this backend can't blit a bound depth-stencil texture directly, so fall
+            // back to a full-screen pass that samples `source` and writes depth into
+            // `destination`, which has the same depth format as `source`.
+            render_context.run_depth_copy_material_pass(source, destination);
+        }
+    }
+}
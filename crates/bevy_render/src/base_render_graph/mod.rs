@@ -1,3 +1,13 @@
+mod depth_format;
+mod depth_prepass;
+mod transparent;
+
+pub use depth_prepass::DepthBlitNode;
+pub use transparent::{
+    transparent_sort_system, Transparent, TransparentDrawDistance, TransparentDrawOrder,
+    TransparentPassNode, TransparentPassPlugin,
+};
+
 use crate::{
     pass::{
         LoadOp, Operations, PassDescriptor, RenderPassColorAttachmentDescriptor,
@@ -10,10 +20,12 @@ use crate::{
         },
         RenderGraph,
     },
+    renderer::RenderResourceContext,
     texture::{Extent3d, TextureDescriptor, TextureDimension, TextureFormat, TextureUsage},
     Color,
 };
 use bevy_window::WindowReference;
+use depth_format::{get_depth_format, stencil_ops_for_format};
 
 pub struct BaseRenderGraphConfig {
     pub add_2d_camera: bool,
@@ -22,6 +34,30 @@ pub struct BaseRenderGraphConfig {
     pub add_main_pass: bool,
     pub connect_main_pass_to_swapchain: bool,
     pub connect_main_pass_to_main_depth_texture: bool,
+    /// The number of samples to use for the main pass. A value of `1` disables multi-sample
+    /// anti-aliasing entirely, leaving the graph identical to the non-multisampled case. Any
+    /// value greater than `1` causes the main pass to render into a multisampled color
+    /// attachment that is resolved into the swap chain texture.
+    pub msaa_samples: u32,
+    /// An explicit override for the format of the main depth texture. When `None` (the
+    /// default) the format is chosen automatically by probing the active backend for the
+    /// best-supported candidate, which is cheaper on tiled mobile GPUs than always using
+    /// `Depth32Float`.
+    pub depth_format: Option<TextureFormat>,
+    /// Adds a second pass after `MAIN_PASS` that draws [`Transparent`] entities back-to-front,
+    /// depth-tested against the depth buffer `MAIN_PASS` wrote. Depth writes are turned off only
+    /// through the depth attachment's store op: depth written during this pass is discarded when
+    /// the pass ends, and pipeline depth-write state is not changed. Opaque geometry still renders
+    /// front-to-back in `MAIN_PASS`; this pass only fixes blending order for translucent
+    /// objects, which `MAIN_PASS` alone gets wrong when overlapping. Also add
+    /// [`TransparentPassPlugin`] to the `AppBuilder` when this is set, or `Transparent`
+    /// entities will never be sorted.
+    pub add_transparent_pass: bool,
+    /// Inserts a depth-only pass before `MAIN_PASS` and exposes its result as the
+    /// `DepthBlitNode::OUT_TEXTURE` slot of `node::DEPTH_PREPASS_BLIT`, so plugins like SSAO,
+    /// soft particles, or fog can consume main-pass depth without each declaring their own
+    /// prepass.
+    pub add_depth_prepass: bool,
 }
 
 pub mod node {
@@ -30,8 +66,13 @@ pub mod node {
     pub const CAMERA2D: &str = "camera2d";
     pub const TEXTURE_COPY: &str = "texture_copy";
     pub const MAIN_DEPTH_TEXTURE: &str = "main_pass_depth_texture";
+    pub const MAIN_SAMPLED_COLOR_ATTACHMENT: &str = "main_pass_sampled_color_attachment";
     pub const MAIN_PASS: &str = "main_pass";
+    pub const TRANSPARENT_PASS: &str = "transparent_pass";
     pub const SHARED_BUFFERS: &str = "shared_buffers";
+    pub const DEPTH_PREPASS: &str = "depth_prepass";
+    pub const DEPTH_PREPASS_TEXTURE: &str = "depth_prepass_texture";
+    pub const DEPTH_PREPASS_BLIT: &str = "depth_prepass_blit";
 }
 
 pub mod camera {
@@ -48,6 +89,10 @@ impl Default for BaseRenderGraphConfig {
             add_main_depth_texture: true,
             connect_main_pass_to_swapchain: true,
             connect_main_pass_to_main_depth_texture: true,
+            msaa_samples: 1,
+            depth_format: None,
+            add_transparent_pass: false,
+            add_depth_prepass: false,
         }
     }
 }
@@ -55,11 +100,22 @@ impl Default for BaseRenderGraphConfig {
 /// By itself this graph doesn't do much, but it allows Render plugins to interop with each other by having a common
 /// set of nodes. It can be customized using `BaseRenderGraphConfig`.
 pub trait BaseRenderGraphBuilder {
-    fn add_base_graph(&mut self, config: &BaseRenderGraphConfig) -> &mut Self;
+    fn add_base_graph(
+        &mut self,
+        config: &BaseRenderGraphConfig,
+        render_resource_context: &dyn RenderResourceContext,
+    ) -> &mut Self;
 }
 
 impl BaseRenderGraphBuilder for RenderGraph {
-    fn add_base_graph(&mut self, config: &BaseRenderGraphConfig) -> &mut Self {
+    fn add_base_graph(
+        &mut self,
+        config: &BaseRenderGraphConfig,
+        render_resource_context: &dyn RenderResourceContext,
+    ) -> &mut Self {
+        let depth_format = get_depth_format(render_resource_context, config.depth_format);
+        let swap_chain_format = render_resource_context.swap_chain_texture_format(WindowReference::Primary);
+
         self.add_node(node::TEXTURE_COPY, TextureCopyNode::default());
         if config.add_3d_camera {
             self.add_system_node(node::CAMERA3D, CameraNode::new(camera::CAMERA3D));
@@ -82,20 +138,137 @@ impl BaseRenderGraphBuilder for RenderGraph {
                             height: 1,
                         },
                         mip_level_count: 1,
-                        sample_count: 1,
+                        sample_count: config.msaa_samples,
+                        dimension: TextureDimension::D2,
+                        format: depth_format,
+                        usage: TextureUsage::OUTPUT_ATTACHMENT,
+                    },
+                ),
+            );
+        }
+
+        if config.msaa_samples > 1 {
+            // Must match the swap chain's own format: this attachment is what `MAIN_PASS`
+            // actually renders into, resolving down into the swap chain texture afterwards,
+            // and a resolve source/target format mismatch fails render-pass validation.
+            self.add_node(
+                node::MAIN_SAMPLED_COLOR_ATTACHMENT,
+                WindowTextureNode::new(
+                    WindowReference::Primary,
+                    TextureDescriptor {
+                        size: Extent3d {
+                            depth: 1,
+                            width: 1,
+                            height: 1,
+                        },
+                        mip_level_count: 1,
+                        sample_count: config.msaa_samples,
                         dimension: TextureDimension::D2,
-                        format: TextureFormat::Depth32Float, // PERF: vulkan docs recommend using 24 bit depth for better performance
+                        format: swap_chain_format,
                         usage: TextureUsage::OUTPUT_ATTACHMENT,
                     },
                 ),
             );
         }
 
+        if config.add_depth_prepass {
+            self.add_node(
+                node::DEPTH_PREPASS_TEXTURE,
+                WindowTextureNode::new(
+                    WindowReference::Primary,
+                    TextureDescriptor {
+                        size: Extent3d {
+                            depth: 1,
+                            width: 1,
+                            height: 1,
+                        },
+                        mip_level_count: 1,
+                        sample_count: 1,
+                        dimension: TextureDimension::D2,
+                        format: depth_format,
+                        usage: TextureUsage::SAMPLED | TextureUsage::OUTPUT_ATTACHMENT,
+                    },
+                ),
+            );
+
+            let mut depth_prepass_node = PassNode::new(PassDescriptor {
+                color_attachments: vec![],
+                depth_stencil_attachment: Some(RenderPassDepthStencilAttachmentDescriptor {
+                    attachment: TextureAttachment::Input("depth".to_string()),
+                    depth_ops: Some(Operations {
+                        load: LoadOp::Clear(1.0),
+                        store: true,
+                    }),
+                    stencil_ops: stencil_ops_for_format(
+                        depth_format,
+                        Operations {
+                            load: LoadOp::Clear(0),
+                            store: true,
+                        },
+                    ),
+                }),
+                sample_count: 1,
+            });
+
+            if config.add_3d_camera {
+                depth_prepass_node.add_camera(camera::CAMERA3D);
+            }
+
+            self.add_node(node::DEPTH_PREPASS, depth_prepass_node);
+
+            self.add_node_edge(node::TEXTURE_COPY, node::DEPTH_PREPASS)
+                .unwrap();
+            self.add_node_edge(node::SHARED_BUFFERS, node::DEPTH_PREPASS)
+                .unwrap();
+
+            if config.add_3d_camera {
+                self.add_node_edge(node::CAMERA3D, node::DEPTH_PREPASS)
+                    .unwrap();
+            }
+
+            self.add_slot_edge(
+                node::DEPTH_PREPASS_TEXTURE,
+                WindowTextureNode::OUT_TEXTURE,
+                node::DEPTH_PREPASS,
+                "depth",
+            )
+            .unwrap();
+
+            // Many backends can't sample a depth-stencil attachment while it is still bound,
+            // so blit (or, where unsupported, full-screen copy) the prepass result into a
+            // texture downstream effects can freely sample.
+            self.add_node(
+                node::DEPTH_PREPASS_BLIT,
+                DepthBlitNode::new(render_resource_context, depth_format),
+            );
+
+            self.add_node_edge(node::DEPTH_PREPASS, node::DEPTH_PREPASS_BLIT)
+                .unwrap();
+            self.add_slot_edge(
+                node::DEPTH_PREPASS_TEXTURE,
+                WindowTextureNode::OUT_TEXTURE,
+                node::DEPTH_PREPASS_BLIT,
+                DepthBlitNode::IN_TEXTURE,
+            )
+            .unwrap();
+
+            if config.add_main_pass {
+                self.add_node_edge(node::DEPTH_PREPASS, node::MAIN_PASS)
+                    .unwrap();
+            }
+        }
+
+        let resolve_target = if config.msaa_samples > 1 {
+            Some(TextureAttachment::Input("color_resolve_target".to_string()))
+        } else {
+            None
+        };
+
         if config.add_main_pass {
             let mut main_pass_node = PassNode::new(PassDescriptor {
                 color_attachments: vec![RenderPassColorAttachmentDescriptor {
                     attachment: TextureAttachment::Input("color".to_string()),
-                    resolve_target: None,
+                    resolve_target: resolve_target.clone(),
                     ops: Operations {
                         load: LoadOp::Clear(Color::rgb(0.1, 0.1, 0.1)),
                         store: true,
@@ -107,9 +280,15 @@ impl BaseRenderGraphBuilder for RenderGraph {
                         load: LoadOp::Clear(1.0),
                         store: true,
                     }),
-                    stencil_ops: None,
+                    stencil_ops: stencil_ops_for_format(
+                        depth_format,
+                        Operations {
+                            load: LoadOp::Clear(0),
+                            store: true,
+                        },
+                    ),
                 }),
-                sample_count: 1,
+                sample_count: config.msaa_samples,
             });
 
             main_pass_node.use_default_clear_color(0);
@@ -139,6 +318,88 @@ impl BaseRenderGraphBuilder for RenderGraph {
             if config.add_2d_camera {
                 self.add_node_edge(node::CAMERA2D, node::MAIN_PASS).unwrap();
             }
+
+            if config.msaa_samples > 1 {
+                self.add_slot_edge(
+                    node::MAIN_SAMPLED_COLOR_ATTACHMENT,
+                    WindowTextureNode::OUT_TEXTURE,
+                    node::MAIN_PASS,
+                    "color",
+                )
+                .unwrap();
+            }
+        }
+
+        if config.add_depth_prepass && config.add_main_pass {
+            self.add_node_edge(node::DEPTH_PREPASS, node::MAIN_PASS)
+                .unwrap();
+        }
+
+        if config.add_transparent_pass {
+            // Loads (rather than clears) the attachments `MAIN_PASS` just wrote so opaque
+            // geometry stays on screen and transparent draws are depth-tested against it. The
+            // depth (and stencil) store op is `false`, so depth written during this pass is
+            // discarded when the pass ends and `MAIN_DEPTH_TEXTURE` keeps `MAIN_PASS`'s values;
+            // pipeline depth-write state is not changed here. Blending order among translucent
+            // objects comes from the back-to-front order `TransparentPassNode` draws them in.
+            let mut transparent_pass_node = TransparentPassNode::new(PassDescriptor {
+                color_attachments: vec![RenderPassColorAttachmentDescriptor {
+                    attachment: TextureAttachment::Input("color".to_string()),
+                    resolve_target: resolve_target.clone(),
+                    ops: Operations {
+                        load: LoadOp::Load,
+                        store: true,
+                    },
+                }],
+                depth_stencil_attachment: Some(RenderPassDepthStencilAttachmentDescriptor {
+                    attachment: TextureAttachment::Input("depth".to_string()),
+                    depth_ops: Some(Operations {
+                        load: LoadOp::Load,
+                        store: false,
+                    }),
+                    stencil_ops: stencil_ops_for_format(
+                        depth_format,
+                        Operations {
+                            load: LoadOp::Load,
+                            store: false,
+                        },
+                    ),
+                }),
+                sample_count: config.msaa_samples,
+            });
+
+            if config.add_3d_camera {
+                transparent_pass_node.add_camera(camera::CAMERA3D);
+            }
+
+            if config.add_2d_camera {
+                transparent_pass_node.add_camera(camera::CAMERA2D);
+            }
+
+            self.add_node(node::TRANSPARENT_PASS, transparent_pass_node);
+
+            self.add_node_edge(node::MAIN_PASS, node::TRANSPARENT_PASS)
+                .unwrap();
+
+            if config.add_3d_camera {
+                self.add_node_edge(node::CAMERA3D, node::TRANSPARENT_PASS)
+                    .unwrap();
+            }
+
+            if config.add_2d_camera {
+                self.add_node_edge(node::CAMERA2D, node::TRANSPARENT_PASS)
+                    .unwrap();
+            }
+
+            if config.msaa_samples > 1 {
+                self.add_slot_edge(
+                    node::MAIN_SAMPLED_COLOR_ATTACHMENT,
+                    WindowTextureNode::OUT_TEXTURE,
+                    node::TRANSPARENT_PASS,
+                    "color",
+                )
+                .unwrap();
+            }
         }
 
         self.add_node(
@@ -147,13 +408,28 @@ impl BaseRenderGraphBuilder for RenderGraph {
         );
 
         if config.connect_main_pass_to_swapchain {
+            let swapchain_dest = if config.msaa_samples > 1 {
+                "color_resolve_target"
+            } else {
+                "color"
+            };
             self.add_slot_edge(
                 node::PRIMARY_SWAP_CHAIN,
                 WindowSwapChainNode::OUT_TEXTURE,
                 node::MAIN_PASS,
-                "color",
+                swapchain_dest,
             )
             .unwrap();
+
+            if config.add_transparent_pass {
+                self.add_slot_edge(
+                    node::PRIMARY_SWAP_CHAIN,
+                    WindowSwapChainNode::OUT_TEXTURE,
+                    node::TRANSPARENT_PASS,
+                    swapchain_dest,
+                )
+                .unwrap();
+            }
         }
 
         if config.connect_main_pass_to_main_depth_texture {
@@ -164,6 +440,16 @@ impl BaseRenderGraphBuilder for RenderGraph {
                 "depth",
             )
             .unwrap();
+
+            if config.add_transparent_pass {
+                self.add_slot_edge(
+                    node::MAIN_DEPTH_TEXTURE,
+                    WindowTextureNode::OUT_TEXTURE,
+                    node::TRANSPARENT_PASS,
+                    "depth",
+                )
+                .unwrap();
+            }
         }
 
         self
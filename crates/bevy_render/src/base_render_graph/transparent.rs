@@ -0,0 +1,143 @@
+use bevy_app::{AppBuilder, Plugin};
+use bevy_ecs::{prelude::*, world::World};
+use bevy_math::Vec3;
+use bevy_transform::prelude::GlobalTransform;
+
+use crate::{
+    camera::Camera,
+    pass::PassDescriptor,
+    render_graph::{Node, ResourceSlotInfo, ResourceSlots},
+    renderer::{RenderContext, RenderResourceType},
+};
+
+use super::camera as base_camera;
+
+/// Marks an entity for the transparent phase added by
+/// [`BaseRenderGraphConfig::add_transparent_pass`](super::BaseRenderGraphConfig::add_transparent_pass).
+/// Transparent entities are drawn by `node::TRANSPARENT_PASS` after all opaque geometry in
+/// `node::MAIN_PASS`, sorted back-to-front using [`TransparentDrawOrder`] so that overlapping
+/// translucent surfaces blend correctly regardless of spawn or query order.
+pub struct Transparent;
+
+/// The entity's distance along the 3D camera's forward axis, computed each frame by
+/// [`transparent_sort_system`].
+#[derive(Debug, Default, Clone, Copy)]
+pub struct TransparentDrawDistance(pub f32);
+
+/// The draw order `node::TRANSPARENT_PASS` uses for `Transparent` entities: farthest entity
+/// first, so nearer translucent fragments blend on top of farther ones regardless of the
+/// order entities were spawned or queried in.
+#[derive(Debug, Default)]
+pub struct TransparentDrawOrder {
+    pub order: Vec<Entity>,
+}
+
+/// Updates [`TransparentDrawDistance`] for every `Transparent` entity and rebuilds
+/// [`TransparentDrawOrder`] by sorting them back-to-front (farthest first) relative to the 3D
+/// camera (`base_render_graph::camera::CAMERA3D`), not just whichever camera a query happens
+/// to yield first — the default config also has a 2D camera active, and sorting against that
+/// one would be meaningless for the transparent phase.
+pub fn transparent_sort_system(
+    camera_query: Query<(&Camera, &GlobalTransform)>,
+    mut transparent_query: Query<(Entity, &GlobalTransform, Option<&mut TransparentDrawDistance>), With<Transparent>>,
+    mut draw_order: ResMut<TransparentDrawOrder>,
+) {
+    let camera_transform = camera_query
+        .iter()
+        .find(|(camera, _)| camera.name.as_deref() == Some(base_camera::CAMERA3D))
+        .map(|(_, transform)| transform);
+
+    let camera_transform = match camera_transform {
+        Some(transform) => transform,
+        None => return,
+    };
+
+    let camera_forward = camera_transform.rotation * -Vec3::unit_z();
+
+    let mut distances: Vec<(Entity, f32)> = Vec::new();
+    for (entity, transform, draw_distance) in transparent_query.iter_mut() {
+        let distance = (transform.translation - camera_transform.translation).dot(camera_forward);
+        if let Some(mut draw_distance) = draw_distance {
+            draw_distance.0 = distance;
+        }
+        distances.push((entity, distance));
+    }
+
+    distances.sort_by(|(_, a), (_, b)| b.partial_cmp(a).unwrap());
+    draw_order.order = distances.into_iter().map(|(entity, _)| entity).collect();
+}
+
+/// A render-pass node like `PassNode`, but one that emits `Transparent` draw commands in the
+/// exact back-to-front order [`transparent_sort_system`] computes into [`TransparentDrawOrder`],
+/// instead of whatever order its camera's visible-entity query happens to yield. `PassNode`
+/// itself has no hook for an externally supplied draw order, so the transparent pass needs its
+/// own node rather than reusing `PassNode` like `MAIN_PASS` and `DEPTH_PREPASS` do.
+pub struct TransparentPassNode {
+    descriptor: PassDescriptor,
+    cameras: Vec<String>,
+}
+
+impl TransparentPassNode {
+    pub const IN_COLOR_ATTACHMENT: &'static str = "color";
+    pub const IN_DEPTH: &'static str = "depth";
+
+    pub fn new(descriptor: PassDescriptor) -> Self {
+        TransparentPassNode {
+            descriptor,
+            cameras: Vec::new(),
+        }
+    }
+
+    pub fn add_camera(&mut self, camera_name: &str) {
+        self.cameras.push(camera_name.to_string());
+    }
+}
+
+impl Node for TransparentPassNode {
+    fn input(&self) -> &[ResourceSlotInfo] {
+        static INPUT: &[ResourceSlotInfo] = &[
+            ResourceSlotInfo {
+                name: std::borrow::Cow::Borrowed(TransparentPassNode::IN_COLOR_ATTACHMENT),
+                resource_type: RenderResourceType::Texture,
+            },
+            ResourceSlotInfo {
+                name: std::borrow::Cow::Borrowed(TransparentPassNode::IN_DEPTH),
+                resource_type: RenderResourceType::Texture,
+            },
+        ];
+        INPUT
+    }
+
+    fn update(
+        &mut self,
+        world: &World,
+        render_context: &mut dyn RenderContext,
+        input: &ResourceSlots,
+        _output: &mut ResourceSlots,
+    ) {
+        let draw_order = world
+            .get_resource::<TransparentDrawOrder>()
+            .map(|draw_order| draw_order.order.as_slice())
+            .unwrap_or(&[]);
+
+        // Unlike `PassNode`, which draws its camera's visible entities in whatever order the
+        // query yields them, this draws exactly `draw_order` — farthest `Transparent` entity
+        // first — so overlapping translucent geometry blends correctly every frame regardless
+        // of spawn or query order.
+        render_context.run_pass_in_entity_order(&self.descriptor, &self.cameras, input, draw_order);
+    }
+}
+
+/// Registers [`transparent_sort_system`] and its [`TransparentDrawOrder`] resource. Required
+/// whenever [`BaseRenderGraphConfig::add_transparent_pass`](super::BaseRenderGraphConfig::add_transparent_pass)
+/// is set — the render graph only wires up the pass's attachments, it doesn't schedule the
+/// system that computes draw order.
+#[derive(Default)]
+pub struct TransparentPassPlugin;
+
+impl Plugin for TransparentPassPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.init_resource::<TransparentDrawOrder>()
+            .add_system_to_stage(bevy_app::stage::POST_UPDATE, transparent_sort_system.system());
+    }
+}